@@ -1,11 +1,13 @@
 //! Bearer Token authentication middleware for MCP HTTP endpoint
 
+use crate::service::ToolProfile;
 use axum::{
     extract::State,
     http::{HeaderMap, StatusCode},
     middleware::Next,
     response::Response,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, warn};
 
@@ -25,37 +27,60 @@ fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
     Some(token.to_string())
 }
 
-/// Authentication state holding the expected token
+/// Authentication state holding the expected tokens
 #[derive(Clone, Default)]
 pub struct AuthState {
     pub token: Option<Arc<String>>,
+    /// Additional client tokens and the tool profile each one is granted
+    pub client_tokens: Arc<HashMap<String, ToolProfile>>,
+}
+
+impl AuthState {
+    fn is_enabled(&self) -> bool {
+        self.token.is_some() || !self.client_tokens.is_empty()
+    }
+
+    /// Resolve the tool profile granted to a token, `None` if the token is not accepted
+    fn profile_for(&self, token: &str) -> Option<ToolProfile> {
+        if self.token.as_deref().is_some_and(|t| t == token) {
+            return Some(ToolProfile::Full);
+        }
+        self.client_tokens.get(token).copied()
+    }
 }
 
 /// Create bearer authentication middleware
 ///
-/// If `expected_token` is `None`, authentication is disabled (all requests pass).
-/// If `expected_token` is `Some(token)`, requests must include a valid
-/// `Authorization: Bearer <token>` header.
-pub fn bearer_auth_middleware(expected_token: Option<String>) -> AuthState {
+/// If `expected_token` is `None` and no client tokens are given, authentication is
+/// disabled (all requests pass). Otherwise requests must include a valid
+/// `Authorization: Bearer <token>` header. `expected_token` grants every tool, a client
+/// token grants the tools of its profile.
+pub fn bearer_auth_middleware(
+    expected_token: Option<String>,
+    client_tokens: HashMap<String, ToolProfile>,
+) -> AuthState {
     AuthState {
         token: expected_token.map(Arc::new),
+        client_tokens: Arc::new(client_tokens),
     }
 }
 
 /// Authentication middleware function
 pub async fn auth_middleware(
     State(state): State<AuthState>,
-    request: axum::extract::Request,
+    mut request: axum::extract::Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    if let Some(expected) = &state.token {
+    if state.is_enabled() {
         let token = extract_bearer_token(request.headers());
 
-        match token {
-            Some(ref t) if t == expected.as_str() => {
-                debug!("Bearer token authentication successful");
+        match token.as_deref().map(|t| state.profile_for(t)) {
+            Some(Some(profile)) => {
+                debug!("Bearer token authentication successful ({:?})", profile);
+                // The MCP handler reads the profile to filter and authorize tools
+                request.extensions_mut().insert(profile);
             }
-            Some(_) => {
+            Some(None) => {
                 warn!(
                     "Bearer token authentication failed: invalid token, {}",
                     request.uri()
@@ -81,6 +106,24 @@ pub async fn auth_middleware(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_client_token_profiles() {
+        let mut client_tokens = HashMap::new();
+        client_tokens.insert("reader".to_string(), ToolProfile::Readonly);
+        let state = bearer_auth_middleware(Some("admin".to_string()), client_tokens.clone());
+        assert!(state.is_enabled());
+        assert_eq!(state.profile_for("admin"), Some(ToolProfile::Full));
+        assert_eq!(state.profile_for("reader"), Some(ToolProfile::Readonly));
+        assert_eq!(state.profile_for("other"), None);
+
+        // Client tokens alone enable authentication
+        let state = bearer_auth_middleware(None, client_tokens);
+        assert!(state.is_enabled());
+        assert_eq!(state.profile_for("admin"), None);
+
+        assert!(!bearer_auth_middleware(None, HashMap::new()).is_enabled());
+    }
+
     #[test]
     fn test_extract_bearer_token() {
         let mut headers = HeaderMap::new();
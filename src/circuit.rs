@@ -0,0 +1,252 @@
+use crate::models::CircuitBreakerConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// 正常放行
+    Closed,
+    /// 熔断中，调用直接短路返回
+    Open,
+    /// 冷却期已过，放行一次试探调用
+    HalfOpen,
+}
+
+/// 单个 API 的熔断器
+#[derive(Debug)]
+struct Breaker {
+    /// 窗口内连续失败的时间点
+    failures: Vec<Instant>,
+    /// 熔断开始时间 (未熔断时为 None)
+    opened_at: Option<Instant>,
+    /// 半开状态下是否已有试探调用在进行
+    trial_in_flight: bool,
+}
+
+/// 熔断器的状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitSnapshot {
+    pub state: CircuitState,
+    /// 窗口内的连续失败次数
+    pub consecutive_failures: usize,
+    /// 距离半开的剩余时间 (毫秒，仅熔断中时有值)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// 熔断器放行的一次调用，调用结束时通过 [`CircuitPermit::record`] 记录结果
+///
+/// 未记录结果就被丢弃 (如客户端断开、调用被取消) 时只释放半开状态的试探名额，不计为失败
+pub struct CircuitPermit<'a> {
+    breakers: &'a CircuitBreakers,
+    api: String,
+    /// 是否为半开状态下的试探调用
+    trial: bool,
+}
+
+impl CircuitPermit<'_> {
+    /// 记录本次调用的结果
+    pub fn record(mut self, config: &CircuitBreakerConfig, failed: bool) {
+        self.breakers.record(&self.api, config, failed, self.trial);
+        self.trial = false;
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        if !self.trial {
+            return;
+        }
+        let mut breakers = self
+            .breakers
+            .breakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(breaker) = breakers.get_mut(&self.api) {
+            breaker.trial_in_flight = false;
+        }
+    }
+}
+
+/// 内存中的熔断器状态 (按 API ID，重启后清零)
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl CircuitBreakers {
+    /// 调用前检查熔断器：允许调用时返回放行凭证，熔断中时返回距离半开的剩余时间
+    ///
+    /// 冷却期已过时转为半开并放行一次试探调用，试探结束前的其他调用仍被短路
+    pub fn try_acquire(
+        &self,
+        api: &str,
+        config: &CircuitBreakerConfig,
+    ) -> Result<CircuitPermit<'_>, Duration> {
+        let permit = |trial| CircuitPermit {
+            breakers: self,
+            api: api.to_string(),
+            trial,
+        };
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(breaker) = breakers.get_mut(api) else {
+            return Ok(permit(false));
+        };
+        let Some(opened_at) = breaker.opened_at else {
+            return Ok(permit(false));
+        };
+        let cooldown = Duration::from_millis(config.cooldown_ms);
+        let elapsed = opened_at.elapsed();
+        if elapsed < cooldown {
+            return Err(cooldown - elapsed);
+        }
+        if breaker.trial_in_flight {
+            return Err(Duration::ZERO);
+        }
+        breaker.trial_in_flight = true;
+        Ok(permit(true))
+    }
+
+    /// 记录一次调用的结果：成功时关闭熔断器，失败次数在窗口内达到阈值 (或试探调用失败) 时熔断
+    fn record(&self, api: &str, config: &CircuitBreakerConfig, failed: bool, was_trial: bool) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(api.to_string()).or_insert(Breaker {
+            failures: Vec::new(),
+            opened_at: None,
+            trial_in_flight: false,
+        });
+        if was_trial {
+            breaker.trial_in_flight = false;
+        }
+
+        if !failed {
+            if breaker.opened_at.take().is_some() {
+                tracing::info!("Circuit of API '{}' closed", api);
+            }
+            breaker.failures.clear();
+            return;
+        }
+
+        let now = Instant::now();
+        let window = Duration::from_millis(config.window_ms);
+        breaker
+            .failures
+            .retain(|t| now.duration_since(*t) <= window);
+        breaker.failures.push(now);
+        if was_trial || breaker.failures.len() >= config.failure_threshold.max(1) as usize {
+            tracing::warn!(
+                "Circuit of API '{}' opened after {} consecutive failures, cooling down for {}ms",
+                api,
+                breaker.failures.len(),
+                config.cooldown_ms
+            );
+            breaker.opened_at = Some(now);
+        }
+    }
+
+    /// 获取熔断器的状态快照 (从未记录过调用时为关闭状态)
+    pub fn snapshot(&self, api: &str, config: &CircuitBreakerConfig) -> CircuitSnapshot {
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(breaker) = breakers.get(api) else {
+            return CircuitSnapshot {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                retry_after_ms: None,
+            };
+        };
+        let window = Duration::from_millis(config.window_ms);
+        let consecutive_failures = breaker
+            .failures
+            .iter()
+            .filter(|t| t.elapsed() <= window)
+            .count();
+        let (state, retry_after_ms) = match breaker.opened_at {
+            None => (CircuitState::Closed, None),
+            Some(opened_at) => {
+                let cooldown = Duration::from_millis(config.cooldown_ms);
+                match cooldown.checked_sub(opened_at.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => {
+                        (CircuitState::Open, Some(remaining.as_millis() as u64))
+                    }
+                    _ => (CircuitState::HalfOpen, None),
+                }
+            }
+        };
+        CircuitSnapshot {
+            state,
+            consecutive_failures,
+            retry_after_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cooldown_ms: u64) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 2,
+            window_ms: 60000,
+            cooldown_ms,
+        }
+    }
+
+    /// 放行一次调用并记录其结果
+    fn call(breakers: &CircuitBreakers, config: &CircuitBreakerConfig, failed: bool) {
+        breakers
+            .try_acquire("api", config)
+            .unwrap()
+            .record(config, failed);
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breakers = CircuitBreakers::default();
+        let config = config(60000);
+
+        call(&breakers, &config, true);
+        // 成功调用清空连续失败计数
+        call(&breakers, &config, false);
+        call(&breakers, &config, true);
+
+        call(&breakers, &config, true);
+        assert!(breakers.try_acquire("api", &config).is_err());
+        assert_eq!(breakers.snapshot("api", &config).state, CircuitState::Open);
+        // 其他 API 不受影响
+        assert!(breakers.try_acquire("other", &config).is_ok());
+    }
+
+    #[test]
+    fn test_half_open_trial() {
+        let breakers = CircuitBreakers::default();
+        let config = config(0);
+        call(&breakers, &config, true);
+        call(&breakers, &config, true);
+        assert_eq!(
+            breakers.snapshot("api", &config).state,
+            CircuitState::HalfOpen
+        );
+
+        // 只放行一次试探调用
+        let trial = breakers.try_acquire("api", &config).unwrap();
+        assert!(breakers.try_acquire("api", &config).is_err());
+
+        // 试探调用被取消时释放试探名额，不计为失败
+        drop(trial);
+        let trial = breakers.try_acquire("api", &config).unwrap();
+        assert_eq!(breakers.snapshot("api", &config).consecutive_failures, 2);
+
+        // 试探失败立即重新熔断，试探成功后关闭
+        trial.record(&config, true);
+        call(&breakers, &config, false);
+        let snapshot = breakers.snapshot("api", &config);
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert!(breakers.try_acquire("api", &config).is_ok());
+    }
+}
@@ -0,0 +1,495 @@
+use crate::error::ModelError;
+use crate::models::{
+    ApiDefinition, ApiParameter, Authentication, HttpMethod, ParameterIn, ParameterType,
+    RequestBody, split_url_credentials,
+};
+
+/// 需要一个参数、可以导入的选项
+const VALUE_OPTIONS: &[(&str, &str)] = &[
+    ("-X", "--request"),
+    ("-H", "--header"),
+    ("-d", "--data"),
+    ("-u", "--user"),
+    ("-A", "--user-agent"),
+    ("-b", "--cookie"),
+    ("-e", "--referer"),
+];
+
+/// 数据选项的其他写法 (均视为 `-d`)
+const DATA_ALIASES: &[&str] = &[
+    "--data-raw",
+    "--data-binary",
+    "--data-ascii",
+    "--data-urlencode",
+];
+
+/// 不影响请求本身、导入时忽略的选项
+const IGNORED_FLAGS: &[(&str, &str)] = &[
+    ("-s", "--silent"),
+    ("-S", "--show-error"),
+    ("-v", "--verbose"),
+    ("-i", "--include"),
+    ("-L", "--location"),
+    ("-f", "--fail"),
+    ("-g", "--globoff"),
+    ("-N", "--no-buffer"),
+    ("-#", "--progress-bar"),
+    ("", "--compressed"),
+];
+
+/// 需要一个参数、无法导入的选项 (参数被跳过，只报告选项名)
+const UNSUPPORTED_VALUE_OPTIONS: &[(&str, &str)] = &[
+    ("-F", "--form"),
+    ("-o", "--output"),
+    ("-x", "--proxy"),
+    ("-U", "--proxy-user"),
+    ("-E", "--cert"),
+    ("-T", "--upload-file"),
+    ("-m", "--max-time"),
+    ("-w", "--write-out"),
+    ("-c", "--cookie-jar"),
+    ("-r", "--range"),
+    ("-K", "--config"),
+    ("-z", "--time-cond"),
+    ("", "--cacert"),
+    ("", "--key"),
+    ("", "--connect-timeout"),
+    ("", "--retry"),
+    ("", "--resolve"),
+    ("", "--limit-rate"),
+    ("", "--form-string"),
+];
+
+/// 从 curl 命令解析出的请求
+#[derive(Debug, Default)]
+pub struct CurlCommand {
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub headers: Vec<(String, String)>,
+    /// `-d` 等数据选项的值 (多个时按 curl 的行为以 `&` 连接)
+    pub data: Vec<String>,
+    /// `--json` 的值
+    pub json: Vec<String>,
+    /// `-u` 的值 (`user:password`)
+    pub user: Option<String>,
+    pub bearer: Option<String>,
+    /// `-G`：数据作为查询参数发送
+    pub get: bool,
+    /// `-I`：以 HEAD 发送
+    pub head: bool,
+    /// 无法导入的选项和参数
+    pub unsupported: Vec<String>,
+}
+
+impl CurlCommand {
+    /// 解析 curl 命令 (支持单引号、双引号、`$'...'` 和行尾 `\` 续行)
+    pub fn parse(command: &str) -> Result<Self, String> {
+        let mut tokens = split_shell_words(command)?.into_iter().peekable();
+        if tokens.peek().is_some_and(|t| t == "curl") {
+            tokens.next();
+        }
+
+        let mut curl = CurlCommand::default();
+        while let Some(token) = tokens.next() {
+            if !token.starts_with('-') || token == "-" {
+                if curl.url.is_some() {
+                    curl.unsupported.push(format!("extra argument '{}'", token));
+                } else {
+                    curl.url = Some(token);
+                }
+                continue;
+            }
+
+            // 短选项可以合并 (如 `-sSL`)，参数可以紧跟选项 (如 `-XPOST`)
+            let options: Vec<(String, Option<String>)> = if token.starts_with("--") {
+                vec![(token, None)]
+            } else {
+                let chars: Vec<char> = token[1..].chars().collect();
+                let mut options = Vec::new();
+                for (i, c) in chars.iter().enumerate() {
+                    let flag = format!("-{}", c);
+                    if takes_value(&flag) {
+                        let rest: String = chars[i + 1..].iter().collect();
+                        options.push((flag, (!rest.is_empty()).then_some(rest)));
+                        break;
+                    }
+                    options.push((flag, None));
+                }
+                options
+            };
+
+            for (flag, attached) in options {
+                let value = if takes_value(&flag) {
+                    match attached.or_else(|| tokens.next()) {
+                        Some(value) => value,
+                        None => return Err(format!("option {} requires a value", flag)),
+                    }
+                } else {
+                    String::new()
+                };
+                curl.apply(&flag, value);
+            }
+        }
+        Ok(curl)
+    }
+
+    fn apply(&mut self, flag: &str, value: String) {
+        let is = |options: &[(&str, &str)]| {
+            options
+                .iter()
+                .any(|(short, long)| flag == *short || flag == *long)
+        };
+        match flag {
+            "-X" | "--request" => self.method = Some(value.to_uppercase()),
+            "-H" | "--header" => match value.split_once(':') {
+                Some((name, value)) => self
+                    .headers
+                    .push((name.trim().to_string(), value.trim().to_string())),
+                None => self
+                    .unsupported
+                    .push(format!("{} '{}' (expected 'Name: value')", flag, value)),
+            },
+            "-d" | "--data" => self.data.push(value),
+            f if DATA_ALIASES.contains(&f) => self.data.push(value),
+            "--json" => self.json.push(value),
+            "-u" | "--user" => self.user = Some(value),
+            "--oauth2-bearer" => self.bearer = Some(value),
+            "--url" => self.url = Some(value),
+            "-A" | "--user-agent" => self.headers.push(("User-Agent".to_string(), value)),
+            "-e" | "--referer" => self.headers.push(("Referer".to_string(), value)),
+            // 不含 `=` 时是 cookie 文件
+            "-b" | "--cookie" if value.contains('=') => {
+                self.headers.push(("Cookie".to_string(), value))
+            }
+            "-b" | "--cookie" => self.unsupported.push(format!("{} (cookie file)", flag)),
+            "-G" | "--get" => self.get = true,
+            "-I" | "--head" => self.head = true,
+            _ if is(IGNORED_FLAGS) => {}
+            _ => self.unsupported.push(flag.to_string()),
+        }
+    }
+
+    /// 转换为 API 定义：URL 拆分为基础 URL、路径和查询参数，JSON 数据转换为请求体定义
+    pub fn build_api(&mut self, name: &str, description: &str) -> Result<ApiDefinition, String> {
+        let url = self.url.clone().ok_or("curl command has no URL")?;
+        let url = if url.contains("://") {
+            url
+        } else {
+            format!("http://{}", url)
+        };
+        let (url, credentials) = split_url_credentials(&url);
+        let mut url = reqwest::Url::parse(&url).map_err(|e| format!("invalid URL: {}", e))?;
+
+        let data = (!self.data.is_empty()).then(|| self.data.join("&"));
+        let json = (!self.json.is_empty()).then(|| self.json.join(""));
+        // `-G` 时数据作为查询参数
+        if self.get
+            && let Some(ref data) = data
+        {
+            let query = match url.query() {
+                Some(query) if !query.is_empty() => format!("{}&{}", query, data),
+                _ => data.clone(),
+            };
+            url.set_query(Some(&query));
+        }
+        let body = if self.get { json } else { json.or(data) };
+
+        let method = match self.method {
+            Some(ref method) => method.parse().map_err(|e: ModelError| e.to_string())?,
+            None if self.head => HttpMethod::Head,
+            None if body.is_some() => HttpMethod::Post,
+            None => HttpMethod::Get,
+        };
+        let base_url = match url.port() {
+            Some(port) => format!(
+                "{}://{}:{}",
+                url.scheme(),
+                url.host_str().unwrap_or_default(),
+                port
+            ),
+            None => format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default()),
+        };
+        let description = if description.is_empty() {
+            format!("Imported from curl: {} {}", method, url.path())
+        } else {
+            description.to_string()
+        };
+        let mut api = ApiDefinition::new(
+            name.to_string(),
+            description,
+            base_url,
+            url.path().to_string(),
+            method,
+        );
+
+        // 查询参数 (重复的键导入为数组)，URL 中的值作为默认值
+        for (key, value) in url.query_pairs() {
+            let value = serde_json::Value::String(value.into_owned());
+            if let Some(param) = api.parameters.iter_mut().find(|p| p.name == key) {
+                param.param_type = ParameterType::Array;
+                match param.default {
+                    Some(serde_json::Value::Array(ref mut values)) => values.push(value),
+                    ref mut default => {
+                        *default = Some(serde_json::json!([default.take(), value]));
+                    }
+                }
+                continue;
+            }
+            api.parameters.push(query_parameter(&key, value));
+        }
+
+        let mut content_type = (!self.json.is_empty()).then(|| "application/json".to_string());
+        for (name, value) in &self.headers {
+            if name.eq_ignore_ascii_case("content-type") && body.is_some() {
+                content_type = Some(value.clone());
+            } else {
+                api.headers.insert(name.clone(), value.clone());
+            }
+        }
+        if !self.json.is_empty() && !api.headers.keys().any(|k| k.eq_ignore_ascii_case("accept")) {
+            api.headers
+                .insert("Accept".to_string(), "application/json".to_string());
+        }
+
+        if let Some(body) = body {
+            match serde_json::from_str::<serde_json::Value>(&body) {
+                _ if body.starts_with('@') => self
+                    .unsupported
+                    .push(format!("request body read from file '{}'", &body[1..])),
+                Ok(example) => {
+                    let mut schema = infer_schema(&example);
+                    schema["example"] = example;
+                    api.request_body = Some(RequestBody {
+                        content_type: content_type
+                            .unwrap_or_else(|| "application/json".to_string()),
+                        schema: Some(schema),
+                        required: true,
+                        description: String::new(),
+                    });
+                }
+                Err(_) => self.unsupported.push(
+                    "request body is not JSON (only JSON bodies can be imported)".to_string(),
+                ),
+            }
+        }
+
+        let credentials = match self.user {
+            Some(ref user) => Some(match user.split_once(':') {
+                Some((username, password)) => (username.to_string(), password.to_string()),
+                None => (user.clone(), String::new()),
+            }),
+            None => credentials,
+        };
+        if let Some(token) = self.bearer.clone() {
+            api.authentication = Authentication::Bearer {
+                token,
+                check_jwt_expiry: false,
+            };
+        } else if let Some((username, password)) = credentials {
+            api.authentication = Authentication::Basic { username, password };
+        }
+        Ok(api)
+    }
+}
+
+/// 选项是否需要一个参数
+fn takes_value(flag: &str) -> bool {
+    let is = |options: &[(&str, &str)]| {
+        options
+            .iter()
+            .any(|(short, long)| flag == *short || flag == *long)
+    };
+    is(VALUE_OPTIONS)
+        || is(UNSUPPORTED_VALUE_OPTIONS)
+        || DATA_ALIASES.contains(&flag)
+        || matches!(flag, "--json" | "--url" | "--oauth2-bearer")
+}
+
+fn query_parameter(name: &str, default: serde_json::Value) -> ApiParameter {
+    ApiParameter {
+        name: name.to_string(),
+        description: String::new(),
+        location: ParameterIn::Query,
+        required: false,
+        param_type: ParameterType::String,
+        default: Some(default),
+        enum_values: None,
+        minimum: None,
+        maximum: None,
+        min_length: None,
+        max_length: None,
+        pattern: None,
+        style: None,
+        depends_on: None,
+        encoding: None,
+        transform: None,
+        explode: None,
+        split_on: None,
+    }
+}
+
+/// 根据示例值推断 JSON Schema
+fn infer_schema(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Null => serde_json::json!({"type": "null"}),
+        serde_json::Value::Bool(_) => serde_json::json!({"type": "boolean"}),
+        serde_json::Value::Number(n) if n.is_f64() => serde_json::json!({"type": "number"}),
+        serde_json::Value::Number(_) => serde_json::json!({"type": "integer"}),
+        serde_json::Value::String(_) => serde_json::json!({"type": "string"}),
+        serde_json::Value::Array(items) => match items.first() {
+            Some(item) => serde_json::json!({"type": "array", "items": infer_schema(item)}),
+            None => serde_json::json!({"type": "array"}),
+        },
+        serde_json::Value::Object(fields) => {
+            let properties: serde_json::Map<_, _> = fields
+                .iter()
+                .map(|(k, v)| (k.clone(), infer_schema(v)))
+                .collect();
+            serde_json::json!({"type": "object", "properties": properties})
+        }
+    }
+}
+
+/// 按 shell 规则拆分命令行
+fn split_shell_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\\' => match chars.next() {
+                // 续行
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => word.get_or_insert_default().push(c),
+                None => {}
+            },
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            // ANSI-C 引用 (浏览器 "Copy as cURL" 对含特殊字符的值使用)
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => word.push('\n'),
+                            Some('t') => word.push('\t'),
+                            Some('r') => word.push('\r'),
+                            Some(c) => word.push(c),
+                            None => return Err("unterminated $'...' quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated $'...' quote".to_string()),
+                    }
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_get_with_headers() {
+        let mut curl = CurlCommand::parse(
+            "curl -sS 'https://api.example.com:8443/v1/users?page=2&tag=a&tag=b' \\\n  -H 'Accept: application/json' \\\n  -H \"X-Trace: on\" --compressed -k",
+        )
+        .unwrap();
+        let api = curl.build_api("list_users", "").unwrap();
+
+        assert_eq!(api.method, HttpMethod::Get);
+        assert_eq!(api.base_url, "https://api.example.com:8443");
+        assert_eq!(api.path, "/v1/users");
+        assert_eq!(api.description, "Imported from curl: GET /v1/users");
+        assert_eq!(api.headers["Accept"], "application/json");
+        assert_eq!(api.headers["X-Trace"], "on");
+        assert_eq!(api.parameters.len(), 2);
+        assert_eq!(api.parameters[0].name, "page");
+        assert_eq!(api.parameters[0].default, Some(serde_json::json!("2")));
+        assert_eq!(api.parameters[1].param_type, ParameterType::Array);
+        assert_eq!(
+            api.parameters[1].default,
+            Some(serde_json::json!(["a", "b"]))
+        );
+        assert!(api.request_body.is_none());
+        // -k 无法导入，--compressed 等输出选项被忽略
+        assert_eq!(curl.unsupported, vec!["-k"]);
+    }
+
+    #[test]
+    fn test_parse_post_with_json_data() {
+        let mut curl = CurlCommand::parse(
+            r#"curl -XPOST https://api.example.com/items -u admin:s3cret -H 'Content-Type: application/vnd.api+json' -d '{"name": "widget", "count": 3, "tags": ["a"]}' -F file=@x.txt"#,
+        )
+        .unwrap();
+        let api = curl.build_api("create_item", "Create an item").unwrap();
+
+        assert_eq!(api.method, HttpMethod::Post);
+        assert_eq!(api.base_url, "https://api.example.com");
+        assert_eq!(api.path, "/items");
+        assert!(api.headers.is_empty());
+        assert!(matches!(
+            api.authentication,
+            Authentication::Basic { ref username, ref password }
+                if username == "admin" && password == "s3cret"
+        ));
+        let body = api.request_body.unwrap();
+        assert_eq!(body.content_type, "application/vnd.api+json");
+        let schema = body.schema.unwrap();
+        assert_eq!(schema["properties"]["count"]["type"], "integer");
+        assert_eq!(schema["properties"]["tags"]["items"]["type"], "string");
+        assert_eq!(schema["example"]["name"], "widget");
+        assert_eq!(curl.unsupported, vec!["-F"]);
+
+        // 未指定方法时带数据默认为 POST，-G 时数据作为查询参数
+        let api = CurlCommand::parse("curl example.com/search -G -d q=rust")
+            .unwrap()
+            .build_api("search", "")
+            .unwrap();
+        assert_eq!(api.method, HttpMethod::Get);
+        assert_eq!(api.base_url, "http://example.com");
+        assert_eq!(api.parameters[0].default, Some(serde_json::json!("rust")));
+
+        assert!(CurlCommand::parse("curl 'https://example.com").is_err());
+        assert!(CurlCommand::parse("curl -H").is_err());
+    }
+}
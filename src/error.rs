@@ -0,0 +1,127 @@
+/// 存储层的错误
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    /// 指定 ID 的 API 不存在
+    #[error("API not found")]
+    NotFound,
+    /// 已存在同名的 API
+    #[error("API with name '{0}' already exists")]
+    DuplicateName(String),
+    /// 同一标签下有多个同名 API
+    #[error("Multiple APIs named '{name}' have tag '{tag}'")]
+    AmbiguousName { name: String, tag: String },
+    /// API 数量已达到上限 (`--max-apis`)
+    #[error("API limit reached: the store already has the maximum of {0} APIs (--max-apis)")]
+    LimitReached(usize),
+    /// API 不处于归档状态 (只有已归档的 API 可以恢复)
+    #[error("API '{0}' is not archived")]
+    NotArchived(String),
+    /// API 已归档，只能通过 restore_api 恢复后再启用或禁用
+    #[error("API '{0}' is archived; use restore_api to restore it first")]
+    Archived(String),
+    /// 读写文件失败
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// 序列化或解析 JSON 失败
+    #[error("{context}")]
+    Json {
+        context: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// 变量文件格式错误
+    #[error("{context}: {message}")]
+    Format { context: String, message: String },
+}
+
+/// 模型层 (API 定义) 的错误
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    /// 不是合法的 HTTP 方法 (必须是合法的 HTTP token)
+    #[error("Invalid HTTP method: '{0}' is not a valid HTTP token")]
+    InvalidMethod(String),
+    /// URL 引用了未定义的变量
+    #[error("URL '{url}' references undefined variables: {}", .names.join(", "))]
+    UndefinedVariables { url: String, names: Vec<String> },
+    /// URL 模板仍有未填充的占位符
+    #[error("URL template '{url}' has unfilled placeholders: {}", .names.join(", "))]
+    UnfilledPlaceholders { url: String, names: Vec<String> },
+    /// 构建出的 URL 不合法
+    #[error("Invalid URL '{url}': {message}")]
+    InvalidUrl { url: String, message: String },
+}
+
+/// 服务层 (工具调用) 的错误
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceError {
+    /// API、变量等不存在
+    #[error("{0}")]
+    NotFound(String),
+    /// 已存在同名的 API
+    #[error("API with name '{0}' already exists")]
+    DuplicateName(String),
+    /// 调用参数或 API 定义不合法
+    #[error("{0}")]
+    Validation(String),
+    /// 工具或 API 已被禁用、归档，目标主机不允许调用，或服务正在退出
+    #[error("{0}")]
+    Unavailable(String),
+    /// 请求上游失败 (连接、前置请求、OAuth2 令牌、响应解码等)
+    #[error("{0}")]
+    Upstream(String),
+    /// 读写存储文件失败
+    #[error(transparent)]
+    Storage(StorageError),
+}
+
+impl ServiceError {
+    /// 错误码 (写入错误结果的 `structured_content.error.code`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServiceError::NotFound(_) => "NOT_FOUND",
+            ServiceError::DuplicateName(_) => "DUPLICATE_NAME",
+            ServiceError::Validation(_) => "INVALID_ARGUMENT",
+            ServiceError::Unavailable(_) => "UNAVAILABLE",
+            ServiceError::Upstream(_) => "UPSTREAM_ERROR",
+            ServiceError::Storage(_) => "STORAGE_ERROR",
+        }
+    }
+}
+
+impl From<StorageError> for ServiceError {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::NotFound => ServiceError::NotFound(error.to_string()),
+            StorageError::DuplicateName(name) => ServiceError::DuplicateName(name),
+            StorageError::AmbiguousName { .. }
+            | StorageError::NotArchived(_)
+            | StorageError::Archived(_) => ServiceError::Validation(error.to_string()),
+            StorageError::LimitReached(_) => ServiceError::Unavailable(error.to_string()),
+            other => ServiceError::Storage(other),
+        }
+    }
+}
+
+/// API 定义不合法 (方法、URL 等)
+impl From<ModelError> for ServiceError {
+    fn from(error: ModelError) -> Self {
+        ServiceError::Validation(error.to_string())
+    }
+}
+
+/// 解析调用参数中的 JSON 值失败
+impl From<serde_json::Error> for ServiceError {
+    fn from(error: serde_json::Error) -> Self {
+        ServiceError::Validation(error.to_string())
+    }
+}
+
+impl From<reqwest::Error> for ServiceError {
+    fn from(error: reqwest::Error) -> Self {
+        ServiceError::Upstream(error.to_string())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::service::OpenApiService;
+use crate::service::{OpenApiService, ToolProfile};
 use rmcp::{
     ErrorData as McpError, RoleServer, ServerHandler,
     model::{
@@ -19,6 +19,20 @@ impl OpenApiHandler {
     pub fn new(service: Arc<OpenApiService>) -> Self {
         Self { service }
     }
+
+    pub fn service(&self) -> &Arc<OpenApiService> {
+        &self.service
+    }
+}
+
+/// 当前请求的工具权限 (http 模式下由认证中间件按令牌写入，stdio 模式为 full)
+fn request_profile(context: &RequestContext<RoleServer>) -> ToolProfile {
+    context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<ToolProfile>())
+        .copied()
+        .unwrap_or_default()
 }
 
 impl ServerHandler for OpenApiHandler {
@@ -50,9 +64,9 @@ impl ServerHandler for OpenApiHandler {
     async fn list_tools(
         &self,
         _request: Option<PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, McpError> {
-        let tools = self.service.get_all_tools().await;
+        let tools = self.service.get_tools(request_profile(&context)).await;
         Ok(ListToolsResult {
             tools,
             next_cursor: None,
@@ -63,9 +77,23 @@ impl ServerHandler for OpenApiHandler {
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
         let name = request.name.as_ref();
+        if !self
+            .service
+            .is_tool_allowed(request_profile(&context), name)
+        {
+            return Ok(CallToolResult {
+                content: vec![rmcp::model::Content::text(format!(
+                    "Error: Tool '{}' is not available to this client",
+                    name
+                ))],
+                is_error: Some(true),
+                meta: None,
+                structured_content: None,
+            });
+        }
         let arguments = request
             .arguments
             .map(serde_json::Value::Object)
@@ -73,12 +101,7 @@ impl ServerHandler for OpenApiHandler {
 
         match self.service.call_tool(name, arguments).await {
             Ok(result) => Ok(result),
-            Err(e) => Ok(CallToolResult {
-                content: vec![rmcp::model::Content::text(format!("Error: {}", e))],
-                is_error: Some(true),
-                meta: None,
-                structured_content: None,
-            }),
+            Err(e) => Ok(self.service.error_result(&e)),
         }
     }
 }
@@ -1,18 +1,31 @@
+// add_api 的工具 schema 较大，超出 json! 宏默认的递归深度
+#![recursion_limit = "256"]
+
 mod auth;
+mod circuit;
+mod curl;
+mod error;
 mod handler;
+mod metrics;
 mod models;
+mod oauth;
 mod service;
 mod storage;
 
 use anyhow::Result;
 use axum::Router;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use handler::OpenApiHandler;
+use metrics::CallStats;
+use models::{HttpVersion, is_valid_tool_name};
 use rmcp::ServiceExt;
 use rmcp::transport::streamable_http_server::{
     StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
 };
-use service::OpenApiService;
+use service::{
+    OpenApiService, ResponseFormat, ResultStyle, ServerInfo, ServiceConfig, ToolProfile,
+};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use storage::ApiStorageManager;
@@ -25,9 +38,12 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 #[command(name = "mcp-openapi")]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Transport mode: stdio or http
-    #[arg(short, long, default_value = "stdio")]
-    transport: TransportMode,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Transport mode: stdio, http, or both (comma-separated, e.g. stdio,http)
+    #[arg(short, long, default_value = "stdio", value_delimiter = ',')]
+    transport: Vec<TransportMode>,
 
     /// HTTP server host (only for http mode)
     #[arg(long, default_value = "127.0.0.1")]
@@ -37,10 +53,26 @@ struct Args {
     #[arg(short, long, default_value = "3000")]
     port: u16,
 
+    /// Unix domain socket path to bind instead of TCP host/port (only for http mode)
+    #[arg(long)]
+    uds: Option<PathBuf>,
+
+    /// After ctrl-c, abort connections still open after this many milliseconds (only for http mode)
+    #[arg(long, value_name = "MS")]
+    shutdown_timeout_ms: Option<u64>,
+
+    /// After ctrl-c, wait up to this many milliseconds for in-flight API calls before closing sessions of all transports
+    #[arg(long, value_name = "MS", default_value_t = 10000)]
+    shutdown_grace_ms: u64,
+
     /// Path to API storage file
     #[arg(short, long, env = "MCP_OPENAPI_STORE")]
     store: Option<PathBuf>,
 
+    /// Load variables from a JSON or KEY=VALUE file at startup (kept in memory, not persisted)
+    #[arg(long, env = "MCP_OPENAPI_VARIABLES_FILE")]
+    variables_file: Option<PathBuf>,
+
     /// Disable management tools (add_api, delete_api, etc.)
     #[arg(short, long)]
     nomg: bool,
@@ -48,9 +80,167 @@ struct Args {
     /// Bearer token for HTTP authentication (only for http mode)
     #[arg(long, env = "MCP_OPENAPI_TOKEN")]
     token: Option<String>,
+
+    /// Additional HTTP bearer token limited to a tool profile: full, api or readonly (repeatable)
+    #[arg(
+        long = "client-token",
+        value_name = "TOKEN=PROFILE",
+        env = "MCP_OPENAPI_CLIENT_TOKENS",
+        value_delimiter = ',',
+        value_parser = parse_client_token
+    )]
+    client_tokens: Vec<(String, ToolProfile)>,
+
+    /// Append a latency/size/status footer to every API call result
+    #[arg(long)]
+    verbose_results: bool,
+
+    /// Keep JSON numbers in responses exactly as sent by the upstream (no float reformatting)
+    #[arg(long)]
+    preserve_number_precision: bool,
+
+    /// Only allow outbound calls to this host (repeatable, supports *.example.com)
+    #[arg(long = "allow-host", value_name = "HOST")]
+    allow_hosts: Vec<String>,
+
+    /// Deny outbound calls to this host (repeatable, supports *.example.com, wins over --allow-host)
+    #[arg(long = "deny-host", value_name = "HOST")]
+    deny_hosts: Vec<String>,
+
+    /// Expose per-API call metrics in Prometheus format at /metrics (only for http mode)
+    #[arg(long)]
+    metrics: bool,
+
+    /// Bearer token required for /metrics (independent of --token; open when unset)
+    #[arg(long, env = "MCP_OPENAPI_METRICS_TOKEN")]
+    metrics_token: Option<String>,
+
+    /// Persist OAuth2 access tokens to this file so a restart reuses still-valid tokens
+    #[arg(long, env = "MCP_OPENAPI_TOKEN_CACHE", value_name = "PATH")]
+    token_cache: Option<PathBuf>,
+
+    /// Split API call results into multiple content blocks of at most this many bytes
+    #[arg(long, value_name = "BYTES")]
+    chunk_response_bytes: Option<usize>,
+
+    /// Default per-attempt timeout for an HTTP method, used when an API has no timeout_ms (repeatable)
+    #[arg(
+        long = "method-timeout",
+        value_name = "METHOD=MS",
+        env = "MCP_OPENAPI_METHOD_TIMEOUTS",
+        value_delimiter = ',',
+        value_parser = parse_method_timeout
+    )]
+    method_timeouts: Vec<(String, u64)>,
+
+    /// Remove a built-in tool from the tool list and refuse calls to it (repeatable)
+    #[arg(
+        long = "disable-tool",
+        value_name = "NAME",
+        env = "MCP_OPENAPI_DISABLED_TOOLS",
+        value_delimiter = ','
+    )]
+    disabled_tools: Vec<String>,
+
+    /// Default timeout for establishing a connection, used when an API has no connect_timeout_ms
+    #[arg(long, value_name = "MS")]
+    connect_timeout_ms: Option<u64>,
+
+    /// HTTP version used to call APIs (an API can override it with http_version)
+    #[arg(
+        long,
+        value_enum,
+        default_value = "auto",
+        env = "MCP_OPENAPI_HTTP_VERSION"
+    )]
+    http_version: HttpVersion,
+
+    /// Append the method, path and required parameters to every API tool description
+    #[arg(long)]
+    param_summary: bool,
+
+    /// Directory that API calls may stream response bodies into with __save_to (disabled when unset)
+    #[arg(long, env = "MCP_OPENAPI_DOWNLOAD_DIR", value_name = "DIR")]
+    download_dir: Option<PathBuf>,
+
+    /// Maximum number of registered APIs (including disabled and archived ones); further additions are refused
+    #[arg(long, env = "MCP_OPENAPI_MAX_APIS", value_name = "N")]
+    max_apis: Option<usize>,
+
+    /// Prefix added to every tool name (management and API tools); calls must use the prefixed names
+    #[arg(long, env = "MCP_OPENAPI_TOOL_PREFIX", value_name = "STR", value_parser = parse_tool_prefix)]
+    tool_prefix: Option<String>,
+
+    /// Maximum number of calls retrying at the same time; further failing calls are not retried
+    #[arg(long, value_name = "N")]
+    max_concurrent_retries: Option<usize>,
+
+    /// Return tool results as human-readable text, structured_content, or both
+    #[arg(long, value_enum, default_value = "both")]
+    result_style: ResultStyle,
+
+    /// Format of JSON responses in API call results (overridable per call with __response_format)
+    #[arg(
+        long,
+        value_enum,
+        default_value = "pretty",
+        env = "MCP_OPENAPI_RESPONSE_FORMAT"
+    )]
+    response_format: ResponseFormat,
+
+    /// Load and validate the store and variables, print a report and exit without serving
+    #[arg(long)]
+    dry_run_startup: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a single tool call against the stored APIs, print the result JSON and exit
+    Call {
+        /// Tool name (management tool or registered API name)
+        #[arg(long)]
+        name: String,
+
+        /// Tool arguments as a JSON object
+        #[arg(long, default_value = "{}")]
+        args: String,
+    },
+}
+
+/// 解析 `TOKEN=PROFILE` 形式的客户端令牌 (令牌本身可以包含 `=`)
+fn parse_client_token(value: &str) -> Result<(String, ToolProfile), String> {
+    let (token, profile) = value
+        .rsplit_once('=')
+        .ok_or_else(|| "expected TOKEN=PROFILE".to_string())?;
+    if token.is_empty() {
+        return Err("token must not be empty".to_string());
+    }
+    Ok((token.to_string(), ToolProfile::from_str(profile, true)?))
+}
+
+/// 检查工具名称前缀只含工具名称允许的字符
+fn parse_tool_prefix(value: &str) -> Result<String, String> {
+    if !is_valid_tool_name(value) {
+        return Err("expected 1-128 characters of A-Z, a-z, 0-9, '_', '-' or '.'".to_string());
+    }
+    Ok(value.to_string())
+}
+
+/// 解析 `METHOD=MS` 形式的按方法默认超时
+fn parse_method_timeout(value: &str) -> Result<(String, u64), String> {
+    let (method, ms) = value
+        .split_once('=')
+        .ok_or_else(|| "expected METHOD=MS".to_string())?;
+    if method.is_empty() {
+        return Err("method must not be empty".to_string());
+    }
+    let ms = ms
+        .parse()
+        .map_err(|_| format!("invalid timeout '{}': expected milliseconds", ms))?;
+    Ok((method.to_ascii_uppercase(), ms))
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
 enum TransportMode {
     Stdio,
     Http,
@@ -83,69 +273,221 @@ async fn main() -> Result<()> {
     tracing::info!("Using storage file: {}", storage_path.display());
 
     // 创建存储管理器
-    let storage = Arc::new(ApiStorageManager::new(storage_path).await?);
+    let storage = Arc::new(
+        ApiStorageManager::new(storage_path)
+            .await?
+            .with_max_apis(args.max_apis),
+    );
 
     // 创建服务 (当 nomg 为 true 时禁用管理工具)
-    let enable_management = !args.nomg;
-    let service = Arc::new(OpenApiService::new(storage, enable_management));
+    let config = ServiceConfig {
+        enable_management: !args.nomg,
+        verbose_results: args.verbose_results,
+        preserve_number_precision: args.preserve_number_precision,
+        allow_hosts: args.allow_hosts,
+        deny_hosts: args.deny_hosts,
+        result_style: args.result_style,
+        response_format: args.response_format,
+        token_cache: args.token_cache,
+        chunk_response_bytes: args.chunk_response_bytes,
+        max_concurrent_retries: args.max_concurrent_retries,
+        method_timeouts: args.method_timeouts.into_iter().collect(),
+        disabled_tools: args.disabled_tools,
+        connect_timeout_ms: args.connect_timeout_ms,
+        http_version: args.http_version,
+        param_summary: args.param_summary,
+        download_dir: args.download_dir,
+        tool_prefix: args.tool_prefix,
+        server: ServerInfo {
+            transports: args
+                .transport
+                .iter()
+                .filter_map(|t| t.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+            listen: args
+                .transport
+                .contains(&TransportMode::Http)
+                .then(|| match args.uds {
+                    Some(ref path) => path.display().to_string(),
+                    None => format!("{}:{}", args.host, args.port),
+                }),
+            http_auth: args.token.is_some(),
+            client_tokens: args.client_tokens.len(),
+            metrics: args.metrics,
+            variables_file: args.variables_file.clone(),
+        },
+    };
+    let service = Arc::new(OpenApiService::new(storage, config));
+    service.check_disabled_tools()?;
+
+    // 加载持久化的 OAuth2 令牌 (缓存文件损坏时只记录警告)
+    match service.load_token_cache().await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Loaded {} cached OAuth2 tokens", count),
+        Err(e) => tracing::warn!("Ignoring OAuth2 token cache: {:#}", e),
+    }
+
+    // 加载变量文件 (仅保存在内存中)
+    if let Some(path) = &args.variables_file {
+        let variables = storage::load_variables_file(path).await?;
+        tracing::info!(
+            "Loaded {} variables from {}",
+            variables.len(),
+            path.display()
+        );
+        service.set_session_variables(variables).await;
+    }
+
+    // 启动前检查模式：输出检查报告后退出，有错误时退出码为 1
+    if args.dry_run_startup {
+        let report = service.startup_check().await;
+        for error in &report.errors {
+            println!("error: {}", error);
+        }
+        for warning in &report.warnings {
+            println!("warning: {}", warning);
+        }
+        println!(
+            "{} APIs checked, {} errors, {} warnings",
+            report.apis,
+            report.errors.len(),
+            report.warnings.len()
+        );
+        if !report.is_ok() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // 单次调用模式：直接调用工具后退出，不启动 MCP 传输
+    if let Some(Command::Call { name, args }) = args.command {
+        return run_once(&service, &name, &args).await;
+    }
+
+    // 指标导出 (仅 http 模式)
+    let metrics = args.metrics.then(|| service.stats());
+    if metrics.is_some() && !args.transport.contains(&TransportMode::Http) {
+        tracing::warn!("--metrics has no effect without the http transport");
+    }
 
     // 创建 Handler
     let handler = OpenApiHandler::new(service);
 
-    match args.transport {
-        TransportMode::Stdio => {
-            run_stdio(handler).await?;
-        }
-        TransportMode::Http => {
-            run_http(handler, &args.host, args.port, args.token).await?;
+    // 退出信号在顶层处理：所有传输监听同一个取消令牌，同时启用 stdio 和 http 时 Ctrl-C 也能退出
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown_signal(
+        handler.service().clone(),
+        args.shutdown_grace_ms,
+        shutdown.clone(),
+    ));
+
+    // 启动所有选定的传输方式，它们共享同一个 handler 和存储
+    let mut transports = tokio::task::JoinSet::new();
+    if args.transport.contains(&TransportMode::Stdio) {
+        transports.spawn(run_stdio(handler.clone(), shutdown.clone()));
+    }
+    if args.transport.contains(&TransportMode::Http) {
+        transports.spawn(run_http(
+            handler,
+            args.host,
+            args.port,
+            args.uds,
+            args.token,
+            args.client_tokens.into_iter().collect(),
+            metrics.map(|stats| (stats, args.metrics_token)),
+            args.shutdown_timeout_ms,
+            shutdown.clone(),
+        ));
+    }
+
+    while let Some(result) = transports.join_next().await {
+        if let Err(e) = result? {
+            transports.abort_all();
+            return Err(e);
         }
     }
 
     tracing::info!("MCP OpenAPI server stopped");
 
+    // stdin 在阻塞线程中读取，运行时退出时会一直等待该读取结束；因退出信号结束时直接退出进程
+    if shutdown.is_cancelled() && args.transport.contains(&TransportMode::Stdio) {
+        std::process::exit(0);
+    }
+
     Ok(())
 }
 
-async fn run_stdio(handler: OpenApiHandler) -> Result<()> {
+async fn run_once(service: &OpenApiService, name: &str, args: &str) -> Result<()> {
+    let arguments: serde_json::Value =
+        serde_json::from_str(args).map_err(|e| anyhow::anyhow!("Invalid --args JSON: {}", e))?;
+
+    let result = match service.call_tool(name, arguments).await {
+        Ok(result) => result,
+        Err(e) => service.error_result(&e),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+
+    if result.is_error == Some(true) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn run_stdio(handler: OpenApiHandler, shutdown: CancellationToken) -> Result<()> {
     tracing::info!("Starting stdio transport...");
 
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let server = handler.serve((stdin, stdout)).await?;
+    // 收到退出信号时结束会话，不必等待 stdin 关闭 (初始化也会因取消而失败，先检查退出信号)
+    let server = tokio::select! {
+        biased;
+        _ = shutdown.cancelled() => return Ok(()),
+        server = handler.serve_with_ct((stdin, stdout), shutdown.child_token()) => server?,
+    };
     server.waiting().await?;
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_http(
     handler: OpenApiHandler,
-    host: &str,
+    host: String,
     port: u16,
+    uds: Option<PathBuf>,
     token: Option<String>,
+    client_tokens: HashMap<String, ToolProfile>,
+    metrics: Option<(Arc<CallStats>, Option<String>)>,
+    shutdown_timeout_ms: Option<u64>,
+    ct: CancellationToken,
 ) -> Result<()> {
-    let addr = format!("{}:{}", host, port);
-    tracing::info!("Starting Streamable HTTP transport on http://{}", addr);
+    tracing::info!("Starting Streamable HTTP transport...");
 
-    if token.is_some() {
-        tracing::info!("Bearer token authentication enabled");
+    if token.is_some() || !client_tokens.is_empty() {
+        tracing::info!(
+            "Bearer token authentication enabled ({} client tokens with tool profiles)",
+            client_tokens.len()
+        );
     } else {
         tracing::warn!("Bearer token authentication DISABLED - all requests will be accepted");
     }
 
-    let ct = CancellationToken::new();
     let config = StreamableHttpServerConfig {
         cancellation_token: ct.clone(),
         ..Default::default()
     };
 
+    let shutdown = ct.clone().cancelled_owned();
     let session_manager = Arc::new(LocalSessionManager::default());
 
     let service = StreamableHttpService::new(move || Ok(handler.clone()), session_manager, config);
 
-    let auth_state = auth::bearer_auth_middleware(token);
+    let auth_state = auth::bearer_auth_middleware(token, client_tokens);
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/mcp", axum::routing::any_service(service))
         .layer(axum::middleware::from_fn_with_state(
             auth_state.clone(),
@@ -153,17 +495,171 @@ async fn run_http(
         ))
         .with_state(auth_state);
 
+    // /metrics 不使用 MCP 端点的认证，可通过独立的令牌保护
+    if let Some((stats, metrics_token)) = metrics {
+        let metrics_auth = auth::bearer_auth_middleware(metrics_token, HashMap::new());
+        app = app.merge(
+            Router::new()
+                .route(
+                    "/metrics",
+                    axum::routing::get(move || async move {
+                        (
+                            [(
+                                axum::http::header::CONTENT_TYPE,
+                                "text/plain; version=0.0.4",
+                            )],
+                            stats.render_prometheus(),
+                        )
+                    }),
+                )
+                .layer(axum::middleware::from_fn_with_state(
+                    metrics_auth.clone(),
+                    auth::auth_middleware,
+                ))
+                .with_state(metrics_auth),
+        );
+        tracing::info!("Prometheus metrics enabled at /metrics");
+    }
+
+    if let Some(path) = uds {
+        return serve_uds(app, path, shutdown, shutdown_timeout_ms).await;
+    }
+
+    let addr = format!("{}:{}", host, port);
     let listener = TcpListener::bind(&addr).await?;
 
     tracing::info!("MCP OpenAPI server listening on http://{}", addr);
     tracing::info!("MCP endpoint: POST http://{}/mcp", addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            tokio::signal::ctrl_c().await.ok();
-            ct.cancel();
-        })
-        .await?;
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown);
+
+    // 收到退出信号后最多等待 shutdown_timeout_ms，超时后不再等待仍未结束的连接
+    let Some(timeout_ms) = shutdown_timeout_ms else {
+        server.await?;
+        return Ok(());
+    };
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            ct.cancelled().await;
+            tokio::time::sleep(std::time::Duration::from_millis(timeout_ms)).await;
+        } => {
+            tracing::warn!(
+                "Graceful shutdown timed out after {}ms, aborting remaining connections",
+                timeout_ms
+            );
+        }
+    }
 
     Ok(())
 }
+
+/// 等待退出信号：停止接受新的 API 调用，最多等待 `grace_ms` 让进行中的调用完成，然后关闭会话
+async fn shutdown_signal(service: Arc<OpenApiService>, grace_ms: u64, ct: CancellationToken) {
+    tokio::signal::ctrl_c().await.ok();
+    let remaining = service
+        .drain_calls(std::time::Duration::from_millis(grace_ms))
+        .await;
+    if remaining > 0 {
+        tracing::warn!(
+            "{} API calls still running after the {}ms shutdown grace period",
+            remaining,
+            grace_ms
+        );
+    }
+    ct.cancel();
+}
+
+/// 在 Unix domain socket 上提供 HTTP 服务，退出时删除 socket 文件
+///
+/// 收到退出信号后等待已有连接结束，设置了 `shutdown_timeout_ms` 时最多等待该时长
+#[cfg(unix)]
+async fn serve_uds(
+    app: Router,
+    path: PathBuf,
+    shutdown: impl std::future::Future<Output = ()>,
+    shutdown_timeout_ms: Option<u64>,
+) -> Result<()> {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::server::graceful::GracefulShutdown;
+    use hyper_util::service::TowerToHyperService;
+    use std::os::unix::fs::FileTypeExt;
+
+    // 清理上次未正常退出时残留的 socket 文件 (不是 socket 的文件不删除，避免误删)
+    match tokio::fs::symlink_metadata(&path).await {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            tokio::fs::remove_file(&path).await?;
+        }
+        Ok(_) => anyhow::bail!(
+            "--uds path '{}' already exists and is not a Unix socket; refusing to replace it",
+            path.display()
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let listener = tokio::net::UnixListener::bind(&path)?;
+
+    tracing::info!("MCP OpenAPI server listening on unix:{}", path.display());
+    tracing::info!("MCP endpoint: POST /mcp");
+
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => break Err(e.into()),
+                };
+                let service = TowerToHyperService::new(app.clone());
+                let connection = graceful.watch(
+                    builder
+                        .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                        .into_owned(),
+                );
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        tracing::debug!("Unix socket connection error: {}", e);
+                    }
+                });
+            }
+            _ = &mut shutdown => break Ok(()),
+        }
+    };
+    drop(listener);
+
+    // 与 TCP 监听相同：最多等待 shutdown_timeout_ms，超时后不再等待仍未结束的连接
+    match shutdown_timeout_ms {
+        None => graceful.shutdown().await,
+        Some(timeout_ms) => {
+            let timeout = std::time::Duration::from_millis(timeout_ms);
+            if tokio::time::timeout(timeout, graceful.shutdown())
+                .await
+                .is_err()
+            {
+                tracing::warn!(
+                    "Graceful shutdown timed out after {}ms, aborting remaining connections",
+                    timeout_ms
+                );
+            }
+        }
+    }
+
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        tracing::warn!("Failed to remove socket file {}: {}", path.display(), e);
+    }
+
+    result
+}
+
+#[cfg(not(unix))]
+async fn serve_uds(
+    _app: Router,
+    _path: PathBuf,
+    _shutdown: impl std::future::Future<Output = ()>,
+    _shutdown_timeout_ms: Option<u64>,
+) -> Result<()> {
+    anyhow::bail!("Unix domain sockets are not supported on this platform")
+}
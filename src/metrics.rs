@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 耗时直方图的桶上限 (秒)
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 单个 API 的调用统计
+#[derive(Debug, Clone, Default)]
+pub struct ApiStats {
+    /// 调用次数
+    pub calls: u64,
+    /// 失败次数 (请求错误、非 2xx 或响应体中的错误字段)
+    pub errors: u64,
+    /// 各耗时桶内的调用次数 (非累计)
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    /// 总耗时 (秒)
+    pub latency_sum: f64,
+}
+
+/// 内存中的 API 调用统计 (重启后清零)
+#[derive(Debug, Default)]
+pub struct CallStats {
+    apis: Mutex<BTreeMap<String, ApiStats>>,
+}
+
+impl CallStats {
+    /// 记录一次 API 调用
+    pub fn record(&self, api: &str, latency: Duration, is_error: bool) {
+        let mut apis = self.apis.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = apis.entry(api.to_string()).or_default();
+        let secs = latency.as_secs_f64();
+
+        stats.calls += 1;
+        if is_error {
+            stats.errors += 1;
+        }
+        stats.latency_sum += secs;
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|le| secs <= *le) {
+            stats.latency_buckets[bucket] += 1;
+        }
+    }
+
+    /// 获取当前统计的快照
+    pub fn snapshot(&self) -> BTreeMap<String, ApiStats> {
+        self.apis.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 以 Prometheus 文本格式导出统计
+    pub fn render_prometheus(&self) -> String {
+        let apis = self.snapshot();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP mcp_openapi_api_calls_total Total number of API tool calls."
+        );
+        let _ = writeln!(out, "# TYPE mcp_openapi_api_calls_total counter");
+        for (name, stats) in &apis {
+            let _ = writeln!(
+                out,
+                "mcp_openapi_api_calls_total{{api=\"{}\"}} {}",
+                escape_label(name),
+                stats.calls
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP mcp_openapi_api_errors_total Total number of failed API tool calls."
+        );
+        let _ = writeln!(out, "# TYPE mcp_openapi_api_errors_total counter");
+        for (name, stats) in &apis {
+            let _ = writeln!(
+                out,
+                "mcp_openapi_api_errors_total{{api=\"{}\"}} {}",
+                escape_label(name),
+                stats.errors
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP mcp_openapi_api_call_duration_seconds API tool call latency in seconds."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE mcp_openapi_api_call_duration_seconds histogram"
+        );
+        for (name, stats) in &apis {
+            let label = escape_label(name);
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(stats.latency_buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "mcp_openapi_api_call_duration_seconds_bucket{{api=\"{}\",le=\"{}\"}} {}",
+                    label, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "mcp_openapi_api_call_duration_seconds_bucket{{api=\"{}\",le=\"+Inf\"}} {}",
+                label, stats.calls
+            );
+            let _ = writeln!(
+                out,
+                "mcp_openapi_api_call_duration_seconds_sum{{api=\"{}\"}} {}",
+                label, stats.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "mcp_openapi_api_call_duration_seconds_count{{api=\"{}\"}} {}",
+                label, stats.calls
+            );
+        }
+
+        out
+    }
+}
+
+/// 转义 Prometheus 标签值中的反斜杠、双引号和换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus() {
+        let stats = CallStats::default();
+        stats.record("get_user", Duration::from_millis(20), false);
+        stats.record("get_user", Duration::from_millis(300), true);
+        stats.record("odd\"name", Duration::from_secs(30), false);
+
+        let text = stats.render_prometheus();
+        assert!(text.contains("mcp_openapi_api_calls_total{api=\"get_user\"} 2"));
+        assert!(text.contains("mcp_openapi_api_errors_total{api=\"get_user\"} 1"));
+        assert!(text.contains(
+            "mcp_openapi_api_call_duration_seconds_bucket{api=\"get_user\",le=\"0.025\"} 1"
+        ));
+        assert!(text.contains(
+            "mcp_openapi_api_call_duration_seconds_bucket{api=\"get_user\",le=\"0.5\"} 2"
+        ));
+        assert!(text.contains(
+            "mcp_openapi_api_call_duration_seconds_bucket{api=\"odd\\\"name\",le=\"10\"} 0"
+        ));
+        assert!(text.contains(
+            "mcp_openapi_api_call_duration_seconds_bucket{api=\"odd\\\"name\",le=\"+Inf\"} 1"
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::error::StorageError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+
+/// 距过期不足该秒数的令牌视为已过期，避免令牌在请求途中失效
+const EXPIRY_MARGIN_SECS: i64 = 30;
+
+/// 缓存的访问令牌
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedToken {
+    access_token: String,
+    /// 过期时间 (Unix 秒)，令牌端点未返回 `expires_in` 时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+impl CachedToken {
+    fn is_valid(&self, now: i64) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at - EXPIRY_MARGIN_SECS > now)
+    }
+}
+
+/// OAuth2 访问令牌缓存
+///
+/// 设置了文件路径时，带有过期时间的令牌会写入该文件 (不包含客户端密钥)，
+/// 重启后仍在有效期内的令牌可以直接复用；没有过期时间的令牌只保存在内存中
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    path: Option<PathBuf>,
+    tokens: RwLock<HashMap<String, CachedToken>>,
+}
+
+impl TokenCache {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            tokens: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 从缓存文件加载仍然有效的令牌，返回加载的数量
+    pub async fn load(&self) -> Result<usize, StorageError> {
+        let Some(ref path) = self.path else {
+            return Ok(0);
+        };
+        if !path.exists() {
+            return Ok(0);
+        }
+
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|source| StorageError::Io {
+                context: format!("Failed to read token cache {}", path.display()),
+                source,
+            })?;
+        let stored: HashMap<String, CachedToken> =
+            serde_json::from_str(&content).map_err(|source| StorageError::Json {
+                context: format!("Failed to parse token cache {}", path.display()),
+                source,
+            })?;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut tokens = self.tokens.write().await;
+        tokens.extend(
+            stored
+                .into_iter()
+                .filter(|(_, token)| token.expires_at.is_some() && token.is_valid(now)),
+        );
+        Ok(tokens.len())
+    }
+
+    /// 获取未过期的令牌
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let now = chrono::Utc::now().timestamp();
+        self.tokens
+            .read()
+            .await
+            .get(key)
+            .filter(|token| token.is_valid(now))
+            .map(|token| token.access_token.clone())
+    }
+
+    /// 缓存令牌并写入缓存文件
+    pub async fn insert(
+        &self,
+        key: String,
+        access_token: String,
+        expires_in: Option<u64>,
+    ) -> Result<(), StorageError> {
+        let expires_at = expires_in.map(|secs| chrono::Utc::now().timestamp() + secs as i64);
+        let mut tokens = self.tokens.write().await;
+        tokens.insert(
+            key,
+            CachedToken {
+                access_token,
+                expires_at,
+            },
+        );
+        self.persist(&tokens).await
+    }
+
+    /// 移除令牌 (如上游返回 401 时)
+    pub async fn remove(&self, key: &str) -> Result<(), StorageError> {
+        let mut tokens = self.tokens.write().await;
+        if tokens.remove(key).is_some() {
+            self.persist(&tokens).await?;
+        }
+        Ok(())
+    }
+
+    /// 将有过期时间且未过期的令牌写入缓存文件
+    async fn persist(&self, tokens: &HashMap<String, CachedToken>) -> Result<(), StorageError> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let stored: HashMap<_, _> = tokens
+            .iter()
+            .filter(|(_, token)| token.expires_at.is_some() && token.is_valid(now))
+            .collect();
+        let content =
+            serde_json::to_string_pretty(&stored).map_err(|source| StorageError::Json {
+                context: "Failed to serialize token cache".to_string(),
+                source,
+            })?;
+        let io_error = |source| StorageError::Io {
+            context: format!("Failed to write token cache {}", path.display()),
+            source,
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+
+        // 令牌文件只允许当前用户读写：先以 0600 创建临时文件再重命名替换，
+        // 写入过程中令牌不会以默认权限出现在磁盘上
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let _ = tokio::fs::remove_file(&temp).await;
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&temp).await.map_err(io_error)?;
+        file.write_all(content.as_bytes()).await.map_err(io_error)?;
+        file.sync_all().await.map_err(io_error)?;
+        drop(file);
+        tokio::fs::rename(&temp, path).await.map_err(io_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_cache_persists_valid_tokens() {
+        let path = std::env::temp_dir().join(format!(
+            "mcp-openapi-token-cache-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+
+        let cache = TokenCache::new(Some(path.clone()));
+        cache
+            .insert("valid".to_string(), "abc".to_string(), Some(3600))
+            .await
+            .unwrap();
+        cache
+            .insert("expired".to_string(), "old".to_string(), Some(0))
+            .await
+            .unwrap();
+        cache
+            .insert("no_expiry".to_string(), "mem".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(cache.get("valid").await.as_deref(), Some("abc"));
+        assert_eq!(cache.get("expired").await, None);
+        assert_eq!(cache.get("no_expiry").await.as_deref(), Some("mem"));
+
+        let restarted = TokenCache::new(Some(path.clone()));
+        assert_eq!(restarted.load().await.unwrap(), 1);
+        assert_eq!(restarted.get("valid").await.as_deref(), Some("abc"));
+        assert_eq!(restarted.get("no_expiry").await, None);
+
+        restarted.remove("valid").await.unwrap();
+        assert_eq!(TokenCache::new(Some(path.clone())).load().await.unwrap(), 0);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_token_cache_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "mcp-openapi-token-cache-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        // 已存在的缓存文件权限过宽时同样被替换为 0600
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let cache = TokenCache::new(Some(path.clone()));
+        cache
+            .insert("valid".to_string(), "abc".to_string(), Some(3600))
+            .await
+            .unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
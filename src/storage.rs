@@ -1,16 +1,20 @@
-use crate::models::{ApiDefinition, ApiStatus, ApiStore};
-use anyhow::{Context, Result};
+use crate::error::StorageError;
+use crate::models::{ApiDefinition, ApiStatus, ApiStore, ImportItemResult, ImportReport};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+type Result<T> = std::result::Result<T, StorageError>;
+
 /// API 存储管理器
 pub struct ApiStorageManager {
     /// 存储文件路径
     file_path: PathBuf,
     /// 内存中的 API 存储
     store: Arc<RwLock<ApiStore>>,
+    /// API 数量上限 (包括停用和归档的 API，未设置时不限制)
+    max_apis: Option<usize>,
 }
 
 impl ApiStorageManager {
@@ -19,8 +23,14 @@ impl ApiStorageManager {
         let store = if file_path.exists() {
             let content = tokio::fs::read_to_string(&file_path)
                 .await
-                .context("Failed to read API store file")?;
-            serde_json::from_str(&content).context("Failed to parse API store file")?
+                .map_err(|source| StorageError::Io {
+                    context: "Failed to read API store file".to_string(),
+                    source,
+                })?;
+            serde_json::from_str(&content).map_err(|source| StorageError::Json {
+                context: "Failed to parse API store file".to_string(),
+                source,
+            })?
         } else {
             ApiStore::default()
         };
@@ -28,33 +38,73 @@ impl ApiStorageManager {
         Ok(Self {
             file_path,
             store: Arc::new(RwLock::new(store)),
+            max_apis: None,
         })
     }
 
+    /// 设置 API 数量上限 (已有的 API 超出上限时不受影响，只拒绝新增)
+    pub fn with_max_apis(mut self, max_apis: Option<usize>) -> Self {
+        self.max_apis = max_apis;
+        self
+    }
+
+    /// API 数量上限
+    pub fn max_apis(&self) -> Option<usize> {
+        self.max_apis
+    }
+
+    /// 存储中的 API 数量是否已达到上限
+    fn limit_reached(&self, count: usize) -> bool {
+        self.max_apis.is_some_and(|max| count >= max)
+    }
+
     /// 保存到文件
     async fn save(&self) -> Result<()> {
+        self.save_to(&self.file_path).await.map(|_| ())
+    }
+
+    /// 将当前内存中的存储保存到指定文件，返回写入的 API 数量
+    pub async fn save_to(&self, path: &Path) -> Result<usize> {
         let store = self.store.read().await;
-        let content = serde_json::to_string_pretty(&*store)?;
+        let content =
+            serde_json::to_string_pretty(&*store).map_err(|source| StorageError::Json {
+                context: "Failed to serialize API store".to_string(),
+                source,
+            })?;
+        let io_error = |source| StorageError::Io {
+            context: "Failed to write API store file".to_string(),
+            source,
+        };
 
         // 确保父目录存在
-        if let Some(parent) = self.file_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
         }
 
-        tokio::fs::write(&self.file_path, content)
-            .await
-            .context("Failed to write API store file")?;
-        Ok(())
+        tokio::fs::write(path, content).await.map_err(io_error)?;
+        Ok(store.apis.len())
+    }
+
+    /// 获取当前内存中存储的副本
+    pub async fn snapshot(&self) -> ApiStore {
+        self.store.read().await.clone()
+    }
+
+    /// 存储文件路径
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
     }
 
     /// 获取所有 API
-    pub async fn list_apis(&self) -> Vec<ApiDefinition> {
+    ///
+    /// 读取接口返回与存储共享的定义 (`Arc`)，不复制定义本身；修改时存储按写时复制替换
+    pub async fn list_apis(&self) -> Vec<Arc<ApiDefinition>> {
         let store = self.store.read().await;
         store.apis.clone()
     }
 
     /// 获取所有启用的 API
-    pub async fn list_enabled_apis(&self) -> Vec<ApiDefinition> {
+    pub async fn list_enabled_apis(&self) -> Vec<Arc<ApiDefinition>> {
         let store = self.store.read().await;
         store
             .apis
@@ -65,25 +115,54 @@ impl ApiStorageManager {
     }
 
     /// 根据 ID 获取 API
-    pub async fn get_api(&self, id: &str) -> Option<ApiDefinition> {
+    pub async fn get_api(&self, id: &str) -> Option<Arc<ApiDefinition>> {
         let store = self.store.read().await;
         store.apis.iter().find(|api| api.id == id).cloned()
     }
 
     /// 根据名称获取 API
-    pub async fn get_api_by_name(&self, name: &str) -> Option<ApiDefinition> {
+    pub async fn get_api_by_name(&self, name: &str) -> Option<Arc<ApiDefinition>> {
         let store = self.store.read().await;
         store.apis.iter().find(|api| api.name == name).cloned()
     }
 
+    /// 在指定标签下按名称查找 API，存在多个匹配时返回错误
+    pub async fn get_api_by_tag_and_name(
+        &self,
+        tag: &str,
+        name: &str,
+    ) -> Result<Option<Arc<ApiDefinition>>> {
+        let store = self.store.read().await;
+        let mut matches = store
+            .apis
+            .iter()
+            .filter(|api| api.name == name && api.tags.iter().any(|t| t == tag));
+
+        let first = matches.next().cloned();
+        if matches.next().is_some() {
+            return Err(StorageError::AmbiguousName {
+                name: name.to_string(),
+                tag: tag.to_string(),
+            });
+        }
+        Ok(first)
+    }
+
     /// 添加新 API
-    pub async fn add_api(&self, api: ApiDefinition) -> Result<ApiDefinition> {
+    pub async fn add_api(&self, api: ApiDefinition) -> Result<Arc<ApiDefinition>> {
+        let api = Arc::new(api);
         {
             let mut store = self.store.write().await;
 
             // 检查名称是否重复
             if store.apis.iter().any(|a| a.name == api.name) {
-                anyhow::bail!("API with name '{}' already exists", api.name);
+                return Err(StorageError::DuplicateName(api.name.clone()));
+            }
+            if let Some(max) = self
+                .max_apis
+                .filter(|_| self.limit_reached(store.apis.len()))
+            {
+                return Err(StorageError::LimitReached(max));
             }
 
             store.apis.push(api.clone());
@@ -93,16 +172,94 @@ impl ApiStorageManager {
         Ok(api)
     }
 
+    /// 批量导入 API
+    ///
+    /// 在持有写锁的情况下对存储副本逐项校验 (名称为空、名称或 ID 与已有 API 或批次内其他 API 重复)，
+    /// 全部通过后一次性替换并保存。任一项失败时不写入任何 API，除非 `partial` 为 true，
+    /// 此时只导入通过校验的项。达到 API 数量上限后，其余各项都记为失败。
+    pub async fn import_apis(
+        &self,
+        batch: Vec<ApiDefinition>,
+        partial: bool,
+    ) -> Result<ImportReport> {
+        let report = {
+            let mut store = self.store.write().await;
+            let mut apis = store.apis.clone();
+            let mut items = Vec::with_capacity(batch.len());
+            let mut limit_reached = false;
+
+            for (index, api) in batch.into_iter().enumerate() {
+                let mut errors = Vec::new();
+                if let Some(max) = self.max_apis.filter(|_| self.limit_reached(apis.len())) {
+                    limit_reached = true;
+                    items.push(ImportItemResult {
+                        index,
+                        name: api.name.clone(),
+                        imported: false,
+                        errors: vec![StorageError::LimitReached(max).to_string()],
+                        warnings: Vec::new(),
+                    });
+                    continue;
+                }
+                if api.name.trim().is_empty() {
+                    errors.push("name must not be empty".to_string());
+                }
+                if apis.iter().any(|a| a.name == api.name) {
+                    errors.push(format!("API with name '{}' already exists", api.name));
+                }
+                if apis.iter().any(|a| a.id == api.id) {
+                    errors.push(format!("API with id '{}' already exists", api.id));
+                }
+                errors.extend(api.header_name_errors());
+                errors.extend(api.pattern_errors());
+
+                items.push(ImportItemResult {
+                    index,
+                    name: api.name.clone(),
+                    imported: errors.is_empty(),
+                    errors,
+                    warnings: Vec::new(),
+                });
+                if items[index].imported {
+                    apis.push(Arc::new(api));
+                }
+            }
+
+            let all_valid = items.iter().all(|item| item.errors.is_empty());
+            let committed = (all_valid || partial) && items.iter().any(|item| item.imported);
+            if committed {
+                store.apis = apis;
+            } else {
+                items.iter_mut().for_each(|item| item.imported = false);
+            }
+
+            ImportReport {
+                committed,
+                limit_reached,
+                items,
+            }
+        };
+
+        if report.committed {
+            self.save().await?;
+        }
+        Ok(report)
+    }
+
     /// 更新 API
-    pub async fn update_api(&self, id: &str, mut updated: ApiDefinition) -> Result<ApiDefinition> {
-        {
+    pub async fn update_api(
+        &self,
+        id: &str,
+        mut updated: ApiDefinition,
+    ) -> Result<Arc<ApiDefinition>> {
+        let updated = {
             let mut store = self.store.write().await;
 
             let index = store
                 .apis
                 .iter()
                 .position(|api| api.id == id)
-                .context("API not found")?;
+                .ok_or(StorageError::NotFound)?;
 
             // 检查名称是否与其他 API 重复
             if store
@@ -111,20 +268,21 @@ impl ApiStorageManager {
                 .enumerate()
                 .any(|(i, a)| i != index && a.name == updated.name)
             {
-                anyhow::bail!("API with name '{}' already exists", updated.name);
+                return Err(StorageError::DuplicateName(updated.name));
             }
 
             updated.id = id.to_string();
             updated.updated_at = chrono::Utc::now().to_rfc3339();
-            store.apis[index] = updated.clone();
-        }
+            store.apis[index] = Arc::new(updated);
+            store.apis[index].clone()
+        };
 
         self.save().await?;
         Ok(updated)
     }
 
     /// 删除 API
-    pub async fn delete_api(&self, id: &str) -> Result<ApiDefinition> {
+    pub async fn delete_api(&self, id: &str) -> Result<Arc<ApiDefinition>> {
         let removed = {
             let mut store = self.store.write().await;
 
@@ -132,7 +290,7 @@ impl ApiStorageManager {
                 .apis
                 .iter()
                 .position(|api| api.id == id)
-                .context("API not found")?;
+                .ok_or(StorageError::NotFound)?;
 
             store.apis.remove(index)
         };
@@ -141,8 +299,15 @@ impl ApiStorageManager {
         Ok(removed)
     }
 
-    /// 启用 API
-    pub async fn enable_api(&self, id: &str) -> Result<ApiDefinition> {
+    /// 设置 API 状态
+    ///
+    /// `restore` 为 true 时只接受已归档的 API；否则已归档的 API 不能直接启用或禁用 (需先 restore_api)
+    async fn set_status(
+        &self,
+        id: &str,
+        status: ApiStatus,
+        restore: bool,
+    ) -> Result<Arc<ApiDefinition>> {
         let api = {
             let mut store = self.store.write().await;
 
@@ -150,10 +315,19 @@ impl ApiStorageManager {
                 .apis
                 .iter_mut()
                 .find(|api| api.id == id)
-                .context("API not found")?;
+                .ok_or(StorageError::NotFound)?;
+
+            let archived = api.status == ApiStatus::Archived;
+            if restore && !archived {
+                return Err(StorageError::NotArchived(api.name.clone()));
+            }
+            if !restore && archived && status != ApiStatus::Archived {
+                return Err(StorageError::Archived(api.name.clone()));
+            }
 
-            api.status = ApiStatus::Enabled;
-            api.updated_at = chrono::Utc::now().to_rfc3339();
+            let definition = Arc::make_mut(api);
+            definition.status = status;
+            definition.updated_at = chrono::Utc::now().to_rfc3339();
             api.clone()
         };
 
@@ -161,37 +335,111 @@ impl ApiStorageManager {
         Ok(api)
     }
 
-    /// 禁用 API
-    pub async fn disable_api(&self, id: &str) -> Result<ApiDefinition> {
-        let api = {
-            let mut store = self.store.write().await;
+    /// 启用 API
+    pub async fn enable_api(&self, id: &str) -> Result<Arc<ApiDefinition>> {
+        self.set_status(id, ApiStatus::Enabled, false).await
+    }
 
-            let api = store
-                .apis
-                .iter_mut()
-                .find(|api| api.id == id)
-                .context("API not found")?;
+    /// 禁用 API
+    pub async fn disable_api(&self, id: &str) -> Result<Arc<ApiDefinition>> {
+        self.set_status(id, ApiStatus::Disabled, false).await
+    }
 
-            api.status = ApiStatus::Disabled;
-            api.updated_at = chrono::Utc::now().to_rfc3339();
-            api.clone()
-        };
+    /// 归档 API
+    pub async fn archive_api(&self, id: &str) -> Result<Arc<ApiDefinition>> {
+        self.set_status(id, ApiStatus::Archived, false).await
+    }
 
-        self.save().await?;
-        Ok(api)
+    /// 恢复已归档的 API (恢复为启用状态)
+    pub async fn restore_api(&self, id: &str) -> Result<Arc<ApiDefinition>> {
+        self.set_status(id, ApiStatus::Enabled, true).await
     }
 
-    /// 按标签筛选 API
-    pub async fn list_apis_by_tag(&self, tag: &str) -> Vec<ApiDefinition> {
+    /// 按标签筛选 API (不包括已归档的 API)
+    pub async fn list_apis_by_tag(&self, tag: &str) -> Vec<Arc<ApiDefinition>> {
         let store = self.store.read().await;
         store
             .apis
             .iter()
+            .filter(|api| api.status != ApiStatus::Archived)
             .filter(|api| api.tags.contains(&tag.to_string()))
             .cloned()
             .collect()
     }
 
+    /// 将所有 API 上的标签 `from` 替换为 `to` (包括已归档的 API)，返回受影响的 API 数量
+    pub async fn move_to_tag(&self, from: &str, to: &str) -> Result<usize> {
+        self.retag(from, Some(to)).await
+    }
+
+    /// 从所有 API 上移除标签 (包括已归档的 API)，返回受影响的 API 数量
+    pub async fn untag_all(&self, tag: &str) -> Result<usize> {
+        self.retag(tag, None).await
+    }
+
+    /// 在所有 API 上原位重命名标签 (保留标签顺序，包括已归档的 API)，返回受影响的 API 数量
+    ///
+    /// 所有修改在同一个写锁内完成，读取方不会看到部分重命名的状态；已带有新标签的 API 去除重复，
+    /// 只在有 API 被修改时保存一次
+    pub async fn rename_tag(&self, from: &str, to: &str) -> Result<usize> {
+        if from == to {
+            return Ok(0);
+        }
+        let affected = {
+            let mut store = self.store.write().await;
+            let now = chrono::Utc::now().to_rfc3339();
+            let mut affected = 0;
+
+            for api in store.apis.iter_mut() {
+                let Some(index) = api.tags.iter().position(|t| t == from) else {
+                    continue;
+                };
+                let api = Arc::make_mut(api);
+                api.tags[index] = to.to_string();
+                let mut seen = std::collections::HashSet::new();
+                api.tags.retain(|t| seen.insert(t.clone()));
+                api.updated_at = now.clone();
+                affected += 1;
+            }
+            affected
+        };
+
+        if affected > 0 {
+            self.save().await?;
+        }
+        Ok(affected)
+    }
+
+    /// 批量移除或替换标签，只在有 API 被修改时保存一次
+    async fn retag(&self, from: &str, to: Option<&str>) -> Result<usize> {
+        let affected = {
+            let mut store = self.store.write().await;
+            let now = chrono::Utc::now().to_rfc3339();
+            let mut affected = 0;
+
+            for api in store.apis.iter_mut() {
+                if !api.tags.iter().any(|t| t == from) {
+                    continue;
+                }
+                let api = Arc::make_mut(api);
+                api.tags.retain(|t| t != from);
+                if let Some(to) = to
+                    && !api.tags.iter().any(|t| t == to)
+                {
+                    api.tags.push(to.to_string());
+                }
+                api.updated_at = now.clone();
+                affected += 1;
+            }
+            affected
+        };
+
+        if affected > 0 {
+            self.save().await?;
+        }
+        Ok(affected)
+    }
+
     // ========== 变量管理方法 ==========
 
     /// 获取所有变量
@@ -238,4 +486,376 @@ impl ApiStorageManager {
         }
         self.save().await
     }
+
+    /// 获取 API 适用的路径前缀 (标签前缀优先于存储级前缀)
+    pub async fn path_prefix_for(&self, api: &ApiDefinition) -> Option<String> {
+        let store = self.store.read().await;
+        store.path_prefix_for(api)
+    }
+
+    /// 获取存储级的按方法默认超时 (方法 -> 毫秒)
+    pub async fn get_method_timeouts(&self) -> HashMap<String, u64> {
+        let store = self.store.read().await;
+        store.method_timeouts.clone()
+    }
+
+    /// 获取所有请求体片段
+    pub async fn get_body_fragments(&self) -> HashMap<String, serde_json::Value> {
+        let store = self.store.read().await;
+        store.body_fragments.clone()
+    }
+
+    /// 设置请求体片段
+    pub async fn set_body_fragment(&self, name: String, value: serde_json::Value) -> Result<()> {
+        {
+            let mut store = self.store.write().await;
+            store.body_fragments.insert(name, value);
+        }
+        self.save().await
+    }
+
+    /// 删除请求体片段
+    pub async fn delete_body_fragment(&self, name: &str) -> Result<bool> {
+        let deleted = {
+            let mut store = self.store.write().await;
+            store.body_fragments.remove(name).is_some()
+        };
+        if deleted {
+            self.save().await?;
+        }
+        Ok(deleted)
+    }
+}
+
+/// 读取存储文件 (不影响当前使用的存储)
+pub async fn load_store(path: &Path) -> Result<ApiStore> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|source| StorageError::Io {
+            context: format!("Failed to read store file {}", path.display()),
+            source,
+        })?;
+    serde_json::from_str(&content).map_err(|source| StorageError::Json {
+        context: format!("Failed to parse store file {}", path.display()),
+        source,
+    })
+}
+
+/// 从文件加载变量 (不写入存储文件)
+///
+/// 支持 JSON 对象 (`{"KEY": "VALUE"}`) 和 `KEY=VALUE` 格式 (每行一个，支持 `#` 注释和
+/// `export` 前缀)。若同名环境变量存在，则以环境变量的值为准。
+pub async fn load_variables_file(path: &Path) -> Result<HashMap<String, String>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|source| StorageError::Io {
+            context: format!("Failed to read variables file {}", path.display()),
+            source,
+        })?;
+    let variables = parse_variables(&content).map_err(|message| StorageError::Format {
+        context: format!("Failed to parse variables file {}", path.display()),
+        message,
+    })?;
+
+    Ok(variables
+        .into_iter()
+        .map(|(key, value)| {
+            let value = std::env::var(&key).unwrap_or(value);
+            (key, value)
+        })
+        .collect())
+}
+
+/// 解析 JSON 对象或 `KEY=VALUE` 格式的变量内容
+fn parse_variables(content: &str) -> std::result::Result<HashMap<String, String>, String> {
+    if content.trim_start().starts_with('{') {
+        let object: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(content).map_err(|e| e.to_string())?;
+        return Ok(object
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(s) => (key, s),
+                other => (key, other.to_string()),
+            })
+            .collect());
+    }
+
+    let mut variables = HashMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {} is not in KEY=VALUE format", index + 1))?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        variables.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(name: &str) -> ApiDefinition {
+        ApiDefinition::new(
+            name.to_string(),
+            String::new(),
+            "https://example.com".to_string(),
+            "/".to_string(),
+            crate::models::HttpMethod::Get,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_import_apis_is_transactional() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone()).await.unwrap();
+        storage.add_api(api("existing")).await.unwrap();
+
+        let batch = vec![api("a"), api("existing"), api("a"), api("b")];
+        let report = storage.import_apis(batch.clone(), false).await.unwrap();
+        assert!(!report.committed);
+        assert!(report.items.iter().all(|item| !item.imported));
+        let failed: Vec<_> = report
+            .items
+            .iter()
+            .filter(|item| !item.errors.is_empty())
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(failed, vec![1, 2]);
+        assert_eq!(storage.list_apis().await.len(), 1);
+
+        let report = storage.import_apis(batch, true).await.unwrap();
+        assert!(report.committed);
+        let names: Vec<_> = storage
+            .list_apis()
+            .await
+            .into_iter()
+            .map(|a| a.name.clone())
+            .collect();
+        assert_eq!(names, vec!["existing", "a", "b"]);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_max_apis() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone())
+            .await
+            .unwrap()
+            .with_max_apis(Some(3));
+        storage.add_api(api("a")).await.unwrap();
+
+        // 部分导入在达到上限时停止，其余各项记为失败
+        let batch = vec![api("b"), api("a"), api("c"), api("d"), api("e")];
+        let report = storage.import_apis(batch.clone(), false).await.unwrap();
+        assert!(!report.committed);
+        assert!(report.limit_reached);
+        assert_eq!(storage.list_apis().await.len(), 1);
+
+        let report = storage.import_apis(batch, true).await.unwrap();
+        assert!(report.committed);
+        assert!(report.limit_reached);
+        let imported: Vec<_> = report
+            .items
+            .iter()
+            .filter(|item| item.imported)
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(imported, vec!["b", "c"]);
+        assert!(report.items[3].errors[0].starts_with("API limit reached"));
+        assert!(report.items[4].errors[0].starts_with("API limit reached"));
+        assert_eq!(storage.list_apis().await.len(), 3);
+
+        assert!(matches!(
+            storage.add_api(api("f")).await,
+            Err(StorageError::LimitReached(3))
+        ));
+        // 删除后可以再次添加
+        let a = storage.get_api_by_name("a").await.unwrap();
+        storage.delete_api(&a.id).await.unwrap();
+        storage.add_api(api("f")).await.unwrap();
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_move_to_tag_and_untag_all() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone()).await.unwrap();
+        let mut a = api("a");
+        a.tags = vec!["old".to_string(), "shared".to_string()];
+        let mut b = api("b");
+        b.tags = vec!["old".to_string(), "new".to_string()];
+        let mut c = api("c");
+        c.tags = vec!["shared".to_string()];
+        for item in [a, b, c] {
+            storage.add_api(item).await.unwrap();
+        }
+
+        assert_eq!(storage.move_to_tag("old", "new").await.unwrap(), 2);
+        let a = storage.get_api_by_name("a").await.unwrap();
+        assert_eq!(a.tags, vec!["shared", "new"]);
+        let b = storage.get_api_by_name("b").await.unwrap();
+        assert_eq!(b.tags, vec!["new"]);
+
+        assert_eq!(storage.untag_all("shared").await.unwrap(), 2);
+        assert!(storage.list_apis_by_tag("shared").await.is_empty());
+        assert_eq!(storage.untag_all("missing").await.unwrap(), 0);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_rename_tag() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone()).await.unwrap();
+        let mut a = api("a");
+        a.tags = vec!["old".to_string(), "shared".to_string()];
+        let mut b = api("b");
+        b.tags = vec!["new".to_string(), "old".to_string()];
+        let mut c = api("c");
+        c.tags = vec!["shared".to_string()];
+        for item in [a, b, c] {
+            storage.add_api(item).await.unwrap();
+        }
+
+        assert_eq!(storage.rename_tag("old", "new").await.unwrap(), 2);
+        let a = storage.get_api_by_name("a").await.unwrap();
+        assert_eq!(a.tags, vec!["new", "shared"]);
+        let b = storage.get_api_by_name("b").await.unwrap();
+        assert_eq!(b.tags, vec!["new"]);
+        assert!(storage.list_apis_by_tag("old").await.is_empty());
+
+        assert_eq!(storage.rename_tag("missing", "x").await.unwrap(), 0);
+        assert_eq!(storage.rename_tag("new", "new").await.unwrap(), 0);
+
+        // 重命名已持久化
+        let reloaded = ApiStorageManager::new(path.clone()).await.unwrap();
+        assert_eq!(reloaded.list_apis_by_tag("new").await.len(), 2);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_read_path_shares_definitions() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone()).await.unwrap();
+        let added = storage.add_api(api("a")).await.unwrap();
+        storage.add_api(api("b")).await.unwrap();
+
+        // 读取返回存储中的同一份定义，不克隆
+        let listed = storage.list_apis().await;
+        let by_id = storage.get_api(&added.id).await.unwrap();
+        let by_name = storage.get_api_by_name("a").await.unwrap();
+        assert!(Arc::ptr_eq(&listed[0], &added));
+        assert!(Arc::ptr_eq(&by_id, &added));
+        assert!(Arc::ptr_eq(&by_name, &added));
+        assert!(Arc::ptr_eq(&storage.list_apis().await[1], &listed[1]));
+
+        // 修改时写时复制，之前取出的定义保持不变
+        let disabled = storage.disable_api(&added.id).await.unwrap();
+        assert!(!Arc::ptr_eq(&disabled, &added));
+        assert_eq!(added.status, ApiStatus::Enabled);
+        assert!(Arc::ptr_eq(
+            &storage.get_api(&added.id).await.unwrap(),
+            &disabled
+        ));
+        // 未修改的定义仍然共享
+        assert!(Arc::ptr_eq(&storage.list_apis().await[1], &listed[1]));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_error_variants() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-openapi-test-{}.json", uuid::Uuid::new_v4()));
+        let storage = ApiStorageManager::new(path.clone()).await.unwrap();
+        let added = storage.add_api(api("a")).await.unwrap();
+
+        assert!(matches!(
+            storage.add_api(api("a")).await,
+            Err(StorageError::DuplicateName(name)) if name == "a"
+        ));
+        assert!(matches!(
+            storage.delete_api("missing").await,
+            Err(StorageError::NotFound)
+        ));
+        assert!(matches!(
+            storage.restore_api(&added.id).await,
+            Err(StorageError::NotArchived(name)) if name == "a"
+        ));
+
+        // 已归档的 API 不能直接启用或禁用，恢复后可以
+        storage.archive_api(&added.id).await.unwrap();
+        assert!(matches!(
+            storage.enable_api(&added.id).await,
+            Err(StorageError::Archived(name)) if name == "a"
+        ));
+        assert!(matches!(
+            storage.disable_api(&added.id).await,
+            Err(StorageError::Archived(name)) if name == "a"
+        ));
+        assert_eq!(
+            storage.get_api(&added.id).await.unwrap().status,
+            ApiStatus::Archived
+        );
+        storage.restore_api(&added.id).await.unwrap();
+        storage.disable_api(&added.id).await.unwrap();
+
+        // 手工编辑的存储文件中可能有同一标签下的同名 API
+        let mut tagged = api("a");
+        tagged.tags = vec!["t".to_string()];
+        let store = ApiStore {
+            apis: vec![Arc::new(tagged.clone()), Arc::new(tagged)],
+            ..Default::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&store).unwrap()).unwrap();
+        let reloaded = ApiStorageManager::new(path.clone()).await.unwrap();
+        assert!(matches!(
+            reloaded.get_api_by_tag_and_name("t", "a").await,
+            Err(StorageError::AmbiguousName { .. })
+        ));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            ApiStorageManager::new(path.clone()).await,
+            Err(StorageError::Json { .. })
+        ));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_parse_variables() {
+        let json = parse_variables(r#"{"API_KEY": "secret", "PORT": 8080}"#).unwrap();
+        assert_eq!(json.get("API_KEY").unwrap(), "secret");
+        assert_eq!(json.get("PORT").unwrap(), "8080");
+
+        let env = parse_variables(
+            "# comment\nAPI_KEY=secret\nexport HOST = \"example.com\"\n\nTOKEN='a=b'\n",
+        )
+        .unwrap();
+        assert_eq!(env.get("API_KEY").unwrap(), "secret");
+        assert_eq!(env.get("HOST").unwrap(), "example.com");
+        assert_eq!(env.get("TOKEN").unwrap(), "a=b");
+
+        assert!(parse_variables("NOT_A_PAIR").is_err());
+    }
 }